@@ -33,9 +33,107 @@
 //! tr.set_placeholder_fn("counter", Box::new(counter));
 //! assert_eq!(tr.render().unwrap(), "1 2 3");
 //! ```
+//!
+//! ## Each (block helper)
+//! ```
+//! struct Each(Vec<String>);
+//! impl funcy::BlockFunction for Each {
+//!     fn block_fn_handler<'a>(&mut self, _name: &'a str, _arg: &'a str, body: &'a str) -> Result<String, String> {
+//!         Ok(self.0.iter().map(|item| body.replace("<!$ item>", item)).collect())
+//!     }
+//! }
+//!
+//! let mut tr = funcy::TemplateRenderer::with_template("<!$# each items><!$ item>, <!$/ each>");
+//! tr.set_block_fn("each", Box::new(Each(vec!["a".to_string(), "b".to_string()])));
+//! assert_eq!(tr.render().unwrap(), "a, b, ");
+//! ```
+//!
+//! ## Nested placeholders
+//! ```
+//! struct Upper();
+//! impl funcy::PlaceholderFunction for Upper {
+//!     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+//!         Ok(arg.to_uppercase())
+//!     }
+//! }
+//!
+//! let mut tr = funcy::TemplateRenderer::with_template("<!$ upper <!$ echo World>>");
+//! tr.set_placeholder_fn("upper", Box::new(Upper()));
+//! struct Echo();
+//! impl funcy::PlaceholderFunction for Echo {
+//!     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+//!         Ok(arg.to_string())
+//!     }
+//! }
+//! tr.set_placeholder_fn("echo", Box::new(Echo()));
+//! assert_eq!(tr.render().unwrap(), "WORLD");
+//! ```
+//!
+//! ## Escaping output
+//! ```
+//! struct Bold();
+//! impl funcy::PlaceholderFunction for Bold {
+//!     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, _arg: &'a str) -> Result<String, String> {
+//!         Ok("<b>bold</b>".to_string())
+//!     }
+//! }
+//!
+//! let mut tr = funcy::TemplateRenderer::with_template("<!$ bold>, <!$& bold>");
+//! tr.set_placeholder_fn("bold", Box::new(Bold()));
+//! tr.set_escape_fn(Box::new(funcy::html_escape));
+//! assert_eq!(tr.render().unwrap(), "&lt;b&gt;bold&lt;/b&gt;, <b>bold</b>");
+//! ```
+//!
+//! ## Scripted functions (requires the `script` feature)
+//! ```ignore
+//! let mut tr = funcy::TemplateRenderer::with_template("<!$ upper arg>");
+//! tr.register_script_fn("upper", "arg.to_upper()").unwrap();
+//! assert_eq!(tr.render().unwrap(), "ARG");
+//! ```
+//!
+//! ## Custom delimiters
+//! ```
+//! struct Echo();
+//! impl funcy::PlaceholderFunction for Echo {
+//!     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+//!         Ok(arg.to_string())
+//!     }
+//! }
+//!
+//! let mut tr = funcy::TemplateRenderer::with_template("{{ echo Hello}}, World!").with_delimiters("{{", "}}", ' ');
+//! tr.set_placeholder_fn("echo", Box::new(Echo()));
+//! assert_eq!(tr.render().unwrap(), "Hello, World!");
+//! ```
+//!
+//! ## Structured arguments
+//! ```
+//! use std::collections::HashMap;
+//!
+//! struct Greet();
+//! impl funcy::PlaceholderFunction for Greet {
+//!     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+//!         Ok(arg.to_string())
+//!     }
+//!
+//!     fn placeholder_fn_handler_args<'a>(&mut self, _name: &'a str, _arg: &'a str, positional: &[&'a str], named: &HashMap<&'a str, &'a str>) -> Result<String, String> {
+//!         let greeting = named.get("greeting").copied().unwrap_or("Hello");
+//!         Ok(format!("{}, {}!", greeting, positional.join(" ")))
+//!     }
+//! }
+//!
+//! let mut tr = funcy::TemplateRenderer::with_template("<!$ greet \"John Smith\" greeting=Hi>");
+//! tr.set_placeholder_fn("greet", Box::new(Greet()));
+//! assert_eq!(tr.render().unwrap(), "Hi, John Smith!");
+//! ```
 
 #![warn(missing_docs)]
 
 mod template_renderer;
+mod registry;
 mod tests;
-pub use template_renderer::{TemplateRenderer, PlaceholderFunction, RenderError};
\ No newline at end of file
+#[cfg(feature = "script")]
+mod script;
+pub use template_renderer::{TemplateRenderer, PlaceholderFunction, BlockFunction, RenderError, DEFAULT_MAX_DEPTH, html_escape};
+pub use registry::{TemplateRegistry, TemplateSource};
+#[cfg(feature = "script")]
+pub use script::ScriptFunction;
\ No newline at end of file
@@ -0,0 +1,127 @@
+/*
+Copyright 2020 camc
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+use std::collections::HashMap;
+
+use crate::{BlockFunction, PlaceholderFunction, RenderError, TemplateRenderer};
+
+/// Supplies template strings for a [`TemplateRegistry`], keyed by a logical template id.
+///
+/// Templates are returned as `&'static str`: like Fluent-style translation catalogues, they're expected to be
+/// compiled in (e.g. with `include_str!`) or otherwise kept alive for the life of the program, rather than
+/// re-parsed per request. A source backed by files loaded at runtime can use [`Box::leak`] to satisfy this.
+pub trait TemplateSource {
+    /// Returns the template registered under `id`, or `None` if this source has no such template.
+    fn get_template(&self, id: &str) -> Option<&'static str>;
+}
+
+/// Resolves a logical template id against an ordered list of locales, falling back through [`TemplateSource`]s
+/// the same way Fluent's l10n registry falls back through locales: if the source for a locale has no template
+/// for the id, or rendering it fails (e.g. a placeholder function is missing or errors), the next locale is
+/// tried instead of failing outright.
+///
+/// Sits above a single [`TemplateRenderer`], reusing its registered placeholder/block functions and escape
+/// function across every source that's tried.
+///
+/// # Example
+///
+/// ```
+/// use funcy::{TemplateRegistry, TemplateSource, PlaceholderFunction};
+///
+/// struct StaticSource(&'static str);
+/// impl TemplateSource for StaticSource {
+///     fn get_template(&self, id: &str) -> Option<&'static str> {
+///         if id == "welcome" { Some(self.0) } else { None }
+///     }
+/// }
+///
+/// struct Echo();
+/// impl PlaceholderFunction for Echo {
+///     fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+///         Ok(arg.to_string())
+///     }
+/// }
+///
+/// let mut registry = TemplateRegistry::new();
+/// registry.set_placeholder_fn("echo", Box::new(Echo()));
+/// registry.add_source("de", Box::new(StaticSource("<!$ echo Hallo>")));
+/// registry.add_source("en", Box::new(StaticSource("<!$ echo Hello>")));
+///
+/// // "fr" has no source, so falls back to "de"
+/// assert_eq!(registry.render("welcome", &["fr", "de", "en"]).unwrap(), "Hallo");
+/// // "de" isn't in the list this time, so falls back past "fr" to "en"
+/// assert_eq!(registry.render("welcome", &["fr", "en"]).unwrap(), "Hello");
+/// ```
+pub struct TemplateRegistry {
+    sources: HashMap<String, Box<dyn TemplateSource>>,
+    renderer: TemplateRenderer<'static>
+}
+
+impl Default for TemplateRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TemplateRegistry {
+    /// Creates an empty [`TemplateRegistry`].
+    pub fn new() -> Self {
+        Self { sources: HashMap::new(), renderer: TemplateRenderer::new() }
+    }
+
+    /// Registers `source` under `locale`, e.g. `"de-DE"`. Replaces any source previously registered under the same locale.
+    pub fn add_source(&mut self, locale: &str, source: Box<dyn TemplateSource>) {
+        self.sources.insert(locale.to_string(), source);
+    }
+
+    /// Adds/replaces the specified placeholder function, shared across every source. See [`TemplateRenderer::set_placeholder_fn`].
+    pub fn set_placeholder_fn(&mut self, name: &'static str, thefn: Box<dyn PlaceholderFunction>) {
+        self.renderer.set_placeholder_fn(name, thefn);
+    }
+
+    /// Adds/replaces the specified block function, shared across every source. See [`TemplateRenderer::set_block_fn`].
+    pub fn set_block_fn(&mut self, name: &'static str, thefn: Box<dyn BlockFunction>) {
+        self.renderer.set_block_fn(name, thefn);
+    }
+
+    /// Sets the function used to escape placeholder output, shared across every source. See [`TemplateRenderer::set_escape_fn`].
+    pub fn set_escape_fn(&mut self, escape_fn: Box<dyn Fn(&str) -> String>) {
+        self.renderer.set_escape_fn(escape_fn);
+    }
+
+    /// Resolves `id` against `locales` in order, rendering the first locale whose source both has a template
+    /// for `id` and renders it successfully. Locales with no matching source, or whose source has no template
+    /// for `id`, are skipped silently. If every attempted locale fails to render, returns
+    /// [`RenderError::AllSourcesFailed`] with one error per locale that was actually rendered and failed.
+    pub fn render(&mut self, id: &str, locales: &[&str]) -> Result<String, RenderError<'static>> {
+        let mut errors = Vec::new();
+
+        for locale in locales {
+            let template = match self.sources.get(*locale).and_then(|source| source.get_template(id)) {
+                Some(template) => template,
+                None => continue,
+            };
+
+            self.renderer.set_template(template);
+            match self.renderer.render() {
+                Ok(rendered) => return Ok(rendered),
+                Err(err) => errors.push(err),
+            }
+        }
+
+        Err(RenderError::AllSourcesFailed(errors))
+    }
+}
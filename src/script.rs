@@ -0,0 +1,52 @@
+/*
+Copyright 2020 camc
+
+Licensed under the Apache License, Version 2.0 (the "License");
+you may not use this file except in compliance with the License.
+You may obtain a copy of the License at
+
+    http://www.apache.org/licenses/LICENSE-2.0
+
+Unless required by applicable law or agreed to in writing, software
+distributed under the License is distributed on an "AS IS" BASIS,
+WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+See the License for the specific language governing permissions and
+limitations under the License.
+*/
+
+//! Optional Rhai scripting backend, enabled by the `script` feature. Lets placeholder functions be defined as
+//! script snippets at runtime instead of compiled Rust structs, e.g. from a config file.
+
+use crate::PlaceholderFunction;
+use rhai::{Engine, Scope, AST};
+
+/// A [`PlaceholderFunction`] backed by a compiled Rhai script. Created by [`crate::TemplateRenderer::register_script_fn`].
+///
+/// `name` and `arg` are bound as script variables when the function is called, and the script's result is
+/// converted to a string with Rhai's `to_string`.
+pub struct ScriptFunction {
+    engine: Engine,
+    ast: AST
+}
+
+impl ScriptFunction {
+    /// Compiles `script` into a [`ScriptFunction`]. Returns an error if the script fails to parse.
+    pub fn compile(script: &str) -> Result<Self, String> {
+        let engine = Engine::new();
+        let ast = engine.compile(script).map_err(|err| err.to_string())?;
+        Ok(Self { engine, ast })
+    }
+}
+
+impl PlaceholderFunction for ScriptFunction {
+    fn placeholder_fn_handler<'a>(&mut self, name: &'a str, arg: &'a str) -> Result<String, String> {
+        let mut scope = Scope::new();
+        scope.push("name", name.to_string());
+        scope.push("arg", arg.to_string());
+
+        self.engine
+            .eval_ast_with_scope::<rhai::Dynamic>(&mut scope, &self.ast)
+            .map(|result| result.to_string())
+            .map_err(|err| err.to_string())
+    }
+}
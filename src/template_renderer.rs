@@ -18,9 +18,9 @@ use std::collections::HashMap;
 
 
 /// Renders Funcy template strings.
-/// 
+///
 /// # Example
-/// 
+///
 /// ```
 /// struct Echo();
 /// impl funcy::PlaceholderFunction for Echo {
@@ -33,11 +33,33 @@ use std::collections::HashMap;
 /// tr.set_placeholder_fn("echo", Box::new(Echo()));
 /// assert_eq!(tr.render().unwrap(), "Hello, World!");
 /// ```
-#[derive(Default)]
 pub struct TemplateRenderer<'a> {
     template_str: &'a str,
     placeholders: Vec<PlaceholderExpr<'a>>,
-    placeholder_functions: HashMap<&'a str, Box<dyn PlaceholderFunction>>
+    unclosed_block: Option<&'a str>,
+    max_depth: usize,
+    open_delim: &'a str,
+    close_delim: &'a str,
+    separator: char,
+    escape_fn: Box<dyn Fn(&str) -> String>,
+    placeholder_functions: HashMap<&'a str, Box<dyn PlaceholderFunction>>,
+    block_functions: HashMap<&'a str, Box<dyn BlockFunction>>
+}
+
+/// Default value of [`TemplateRenderer::set_max_depth`], used by [`TemplateRenderer::new`] and [`TemplateRenderer::with_template`].
+pub const DEFAULT_MAX_DEPTH: usize = 32;
+
+/// Default opening delimiter, used by [`TemplateRenderer::new`] and [`TemplateRenderer::with_template`]. See [`TemplateRenderer::with_delimiters`].
+pub const DEFAULT_OPEN_DELIM: &str = "<!$";
+/// Default closing delimiter, used by [`TemplateRenderer::new`] and [`TemplateRenderer::with_template`]. See [`TemplateRenderer::with_delimiters`].
+pub const DEFAULT_CLOSE_DELIM: &str = ">";
+/// Default name/arg separator, used by [`TemplateRenderer::new`] and [`TemplateRenderer::with_template`]. See [`TemplateRenderer::with_delimiters`].
+pub const DEFAULT_SEPARATOR: char = ' ';
+
+impl<'a> Default for TemplateRenderer<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> TemplateRenderer<'a> {
@@ -46,23 +68,60 @@ impl<'a> TemplateRenderer<'a> {
         Self {
             template_str: "",
             placeholders: Vec::new(),
-            placeholder_functions: HashMap::new()
+            unclosed_block: None,
+            max_depth: DEFAULT_MAX_DEPTH,
+            open_delim: DEFAULT_OPEN_DELIM,
+            close_delim: DEFAULT_CLOSE_DELIM,
+            separator: DEFAULT_SEPARATOR,
+            escape_fn: Box::new(|s: &str| s.to_string()),
+            placeholder_functions: HashMap::new(),
+            block_functions: HashMap::new()
         }
     }
 
     /// Creates a [`TemplateRenderer`] with the specified template
     pub fn with_template(inp_str: &'a str) -> Self {
+        let (placeholders, unclosed_block) = parse_placeholders(inp_str, DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
         Self {
             template_str: inp_str,
-            placeholders: parse_placeholders(inp_str),
-            placeholder_functions: HashMap::new()
+            placeholders,
+            unclosed_block,
+            max_depth: DEFAULT_MAX_DEPTH,
+            open_delim: DEFAULT_OPEN_DELIM,
+            close_delim: DEFAULT_CLOSE_DELIM,
+            separator: DEFAULT_SEPARATOR,
+            escape_fn: Box::new(|s: &str| s.to_string()),
+            placeholder_functions: HashMap::new(),
+            block_functions: HashMap::new()
         }
     }
 
     /// Sets the renderer's template string
     pub fn set_template(&mut self, inp_str: &'a str) {
+        let (placeholders, unclosed_block) = parse_placeholders(inp_str, self.open_delim, self.close_delim, self.separator);
         self.template_str = inp_str;
-        self.placeholders = parse_placeholders(inp_str);
+        self.placeholders = placeholders;
+        self.unclosed_block = unclosed_block;
+    }
+
+    /// Sets the delimiters used to recognise placeholder tags, replacing the default `<!$ name arg>` form.
+    /// `open` and `close` may be multiple characters, e.g. `with_delimiters("{{", "}}", ' ')` for
+    /// `{{ name arg}}`-style tags. The block (`#`/`/`) and raw (`&`) markers always immediately follow `open`.
+    /// Re-parses the current template against the new delimiters.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `open` is empty: an empty opening delimiter matches everywhere, which would make tag scanning
+    /// never advance.
+    pub fn with_delimiters(mut self, open: &'a str, close: &'a str, separator: char) -> Self {
+        assert!(!open.is_empty(), "TemplateRenderer::with_delimiters: open delimiter must not be empty");
+        self.open_delim = open;
+        self.close_delim = close;
+        self.separator = separator;
+        let (placeholders, unclosed_block) = parse_placeholders(self.template_str, open, close, separator);
+        self.placeholders = placeholders;
+        self.unclosed_block = unclosed_block;
+        self
     }
 
     /// Adds/replaces the specified placeholder function
@@ -80,27 +139,86 @@ impl<'a> TemplateRenderer<'a> {
         self.placeholder_functions = map;
     }
 
+    /// Adds/replaces the specified block function, used for `<!$# name arg>...<!$/ name>` tags
+    pub fn set_block_fn(&mut self, name: &'a str, thefn: Box<dyn BlockFunction>) {
+        self.block_functions.insert(name, thefn);
+    }
+
+    /// Compiles `script` as a Rhai snippet and registers it as the placeholder function `name`. `name` and `arg`
+    /// are bound as script variables when the placeholder is rendered. Requires the `script` feature.
+    #[cfg(feature = "script")]
+    pub fn register_script_fn(&mut self, name: &'a str, script: &str) -> Result<(), String> {
+        let scriptfn = crate::script::ScriptFunction::compile(script)?;
+        self.set_placeholder_fn(name, Box::new(scriptfn));
+        Ok(())
+    }
+
+    /// Sets the maximum nesting depth allowed when resolving nested placeholders, e.g. `<!$ upper <!$ name>>`.
+    /// Exceeding this depth causes [`RenderError::RecursionLimit`] to be returned from [`TemplateRenderer::render`].
+    /// Defaults to [`DEFAULT_MAX_DEPTH`].
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// Sets the function used to escape every value returned from a [`PlaceholderFunction`] before it's inserted
+    /// into the output. Defaults to the identity function (no escaping). See [`html_escape`] for a built-in escaper
+    /// suitable for rendering into HTML. A placeholder can opt out of escaping with the raw tag form `<!$& name arg>`.
+    pub fn set_escape_fn(&mut self, escape_fn: Box<dyn Fn(&str) -> String>) {
+        self.escape_fn = escape_fn;
+    }
+
     /// Renders the template into a [`String`]
-    pub fn render(&mut self) -> Result<String, RenderError> {
+    pub fn render(&mut self) -> Result<String, RenderError<'a>> {
+        if let Some(name) = self.unclosed_block {
+            return Err(RenderError::UnclosedBlock(name));
+        }
+
         let mut out_str = String::new();
         let mut last_end = 0;
 
+        let mut ctx = ExpandCtx {
+            max_depth: self.max_depth,
+            open: self.open_delim,
+            close: self.close_delim,
+            separator: self.separator,
+            placeholder_functions: &mut self.placeholder_functions,
+            block_functions: &mut self.block_functions,
+            escape_fn: &*self.escape_fn
+        };
+
         for placeholder in &self.placeholders {
             out_str.push_str(&self.template_str[last_end..placeholder.start_idx]);
-            let func: &str;
-            let arg: &str;
-            if placeholder.content.contains(" ") {
-                let fa = split_once(placeholder.content, ' ').unwrap();
-                func = fa.0;
-                arg = fa.1;
+            let (func, raw_arg) = split_name_arg(placeholder.content, ctx.separator);
+            let arg = if raw_arg.contains(ctx.open) {
+                match expand_str(raw_arg, 1, &mut ctx) {
+                    Ok(arg) => arg,
+                    Err(ExpandError::RecursionLimit(max)) => return Err(RenderError::RecursionLimit(max)),
+                    Err(ExpandError::Message(err)) => return Err(RenderError::FunctionError(func, err)),
+                }
             } else {
-                func = placeholder.content;
-                arg = "";
-            }
-
-            if let Some(placeholderfn) = self.placeholder_functions.get_mut(func) {
-                match placeholderfn.placeholder_fn_handler(func, arg) {
-                    Ok(output) => out_str.push_str(&output),
+                raw_arg.to_string()
+            };
+
+            if let Some(body) = placeholder.body {
+                if let Some(blockfn) = ctx.block_functions.get_mut(func) {
+                    match blockfn.block_fn_handler(func, &arg, body) {
+                        Ok(output) => match expand_str(&output, 1, &mut ctx) {
+                            Ok(expanded) => out_str.push_str(&expanded),
+                            Err(ExpandError::RecursionLimit(max)) => return Err(RenderError::RecursionLimit(max)),
+                            Err(ExpandError::Message(err)) => return Err(RenderError::FunctionError(func, err)),
+                        },
+                        Err(err) => return Err(RenderError::FunctionError(func, err)),
+                    }
+                } else {
+                    return Err(RenderError::UnknownFunction(*placeholder));
+                }
+            } else if let Some(placeholderfn) = ctx.placeholder_functions.get_mut(func) {
+                let (positional, named) = match tokenize_args(&arg) {
+                    Ok(parsed) => parsed,
+                    Err(err) => return Err(RenderError::ArgParse(func, err)),
+                };
+                match placeholderfn.placeholder_fn_handler_args(func, &arg, &positional, &named) {
+                    Ok(output) => out_str.push_str(&if placeholder.raw { output } else { (ctx.escape_fn)(&output) }),
                     Err(err) => return Err(RenderError::FunctionError(func, err)),
                 }
             } else {
@@ -128,7 +246,19 @@ pub enum RenderError<'a> {
     UnknownFunction(PlaceholderExpr<'a>),
     /// Returned when a placeholder function returns an error.
     /// The first item is the name of the function, the second is the error string returned.
-    FunctionError(&'a str, String)
+    FunctionError(&'a str, String),
+    /// Returned when a block tag (`<!$# name arg>`) has no matching closing tag (`<!$/ name>`) before the end of the template.
+    /// The item is the name of the unclosed block.
+    UnclosedBlock(&'a str),
+    /// Returned when resolving a nested placeholder, e.g. `<!$ upper <!$ name>>`, exceeds [`TemplateRenderer::set_max_depth`].
+    /// The item is the max depth that was exceeded.
+    RecursionLimit(usize),
+    /// Returned by [`crate::TemplateRegistry::render`] when every attempted locale's source failed to render.
+    /// Contains the error from each locale that was actually rendered and failed, in the order they were tried.
+    AllSourcesFailed(Vec<RenderError<'a>>),
+    /// Returned when a placeholder's argument string has malformed quoting (an unterminated `"`).
+    /// The first item is the name of the function, the second describes the problem.
+    ArgParse(&'a str, String)
 }
 
 impl std::fmt::Display for RenderError<'_> {
@@ -136,6 +266,19 @@ impl std::fmt::Display for RenderError<'_> {
         match self {
             RenderError::UnknownFunction(placeholder) => write!(f, "Unknown function at char {} in placeholder content: '{}'", placeholder.start_idx, placeholder.content),
             RenderError::FunctionError(func, err) => write!(f, "Error in placeholder function {}: '{}'", func, err),
+            RenderError::UnclosedBlock(name) => write!(f, "Unclosed block '{}'", name),
+            RenderError::RecursionLimit(max) => write!(f, "Exceeded max nesting depth of {}", max),
+            RenderError::AllSourcesFailed(errs) => {
+                write!(f, "All sources failed: [")?;
+                for (i, err) in errs.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", err)?;
+                }
+                write!(f, "]")
+            }
+            RenderError::ArgParse(func, err) => write!(f, "Error parsing arguments for function {}: '{}'", func, err),
         }
     }
 }
@@ -144,9 +287,9 @@ impl std::error::Error for RenderError<'_> {}
 
 
 /// Trait used to define functions that can be called from placeholders.
-/// 
+///
 /// # Example
-/// 
+///
 /// ```
 /// struct Echo();
 /// impl funcy::PlaceholderFunction for Echo {
@@ -158,55 +301,310 @@ impl std::error::Error for RenderError<'_> {}
 pub trait PlaceholderFunction {
     /// Called when a placeholder references the function.
     /// The arg may be empty. Errors returned will be propagated and returned from the [`TemplateRenderer::render`] function.
-    /// 
-    /// The name argument includes the name of the placeholder function being called. 
+    ///
+    /// The name argument includes the name of the placeholder function being called.
     /// This can be used to have one struct handle multiple placeholder functions.
     fn placeholder_fn_handler<'a>(&mut self, name: &'a str, arg: &'a str) -> Result<String, String>;
+
+    /// Called when a placeholder references the function, with `arg` already tokenized into `positional`
+    /// arguments and `key=value` pairs. Arguments are whitespace-separated, and either a whole argument or
+    /// just a `key=value`'s value may be double-quoted to include whitespace, e.g. `join "a b" c sep=", "`.
+    /// `arg` is the untokenized argument string, unchanged from [`Self::placeholder_fn_handler`].
+    ///
+    /// Defaults to ignoring the tokenized arguments and delegating to [`Self::placeholder_fn_handler`], so
+    /// functions that only care about the raw argument string don't need to implement this method.
+    fn placeholder_fn_handler_args<'a>(&mut self, name: &'a str, arg: &'a str, positional: &[&'a str], named: &HashMap<&'a str, &'a str>) -> Result<String, String> {
+        let _ = (positional, named);
+        self.placeholder_fn_handler(name, arg)
+    }
+}
+
+/// Trait used to define block functions, called for `<!$# name arg>...<!$/ name>` tags.
+///
+/// # Example
+///
+/// ```
+/// struct Upper();
+/// impl funcy::BlockFunction for Upper {
+///     fn block_fn_handler<'a>(&mut self, _name: &'a str, _arg: &'a str, body: &'a str) -> Result<String, String> {
+///         Ok(body.to_uppercase())
+///     }
+/// }
+/// ```
+pub trait BlockFunction {
+    /// Called when a block placeholder references the function.
+    /// `body` is the raw, unrendered template text between the opening and closing tags.
+    /// The returned string is expanded again, so any placeholders it contains (including ones copied from `body`) are resolved
+    /// before being inserted into the output.
+    fn block_fn_handler<'a>(&mut self, name: &'a str, arg: &'a str, body: &'a str) -> Result<String, String>;
 }
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Default)]
 pub struct PlaceholderExpr<'a> {
     start_idx: usize,
     end_idx: usize,
-    content: &'a str
+    content: &'a str,
+    body: Option<&'a str>,
+    raw: bool
+}
+
+/// Built-in [`TemplateRenderer::set_escape_fn`] escaper suitable for rendering into HTML.
+/// Replaces `&`, `"`, `<` and `>` with their corresponding HTML entities.
+pub fn html_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '"' => out.push_str("&quot;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            _ => out.push(c)
+        }
+    }
+    out
+}
+
+/// Splits a placeholder's content into its function name and argument, e.g. `"name arg"` -> `("name", "arg")`.
+fn split_name_arg(content: &str, separator: char) -> (&str, &str) {
+    match split_once(content, separator) {
+        Some((func, arg)) => (func, arg),
+        None => (content, "")
+    }
+}
+
+/// Splits a placeholder's argument string into whitespace-separated words, treating a double-quoted span
+/// (`"a b"`) as a single word that may itself contain whitespace. Returns the words with their surrounding
+/// quotes (if any) still attached; stripping them is [`strip_quotes`]'s job. Errors if a quote is left
+/// unterminated.
+fn split_args(arg: &str) -> Result<Vec<&str>, String> {
+    let mut words = Vec::new();
+    let bytes = arg.as_bytes();
+    let len = bytes.len();
+    let mut pos = 0;
+
+    while pos < len {
+        while pos < len && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos >= len {
+            break;
+        }
+
+        let start = pos;
+        let mut in_quotes = false;
+        while pos < len {
+            match bytes[pos] {
+                b'"' => in_quotes = !in_quotes,
+                c if c.is_ascii_whitespace() && !in_quotes => break,
+                _ => {}
+            }
+            pos += 1;
+        }
+
+        if in_quotes {
+            return Err(format!("unterminated quote in argument '{}'", &arg[start..]));
+        }
+        words.push(&arg[start..pos]);
+    }
+
+    Ok(words)
 }
 
-const PLACEHOLDER_PARTS: [char; 5] = ['<', '!', '$', ' ', '>'];
-fn parse_placeholders(inp_str: &str) -> Vec<PlaceholderExpr> {
+/// Strips a single pair of surrounding double quotes from `s`, if present.
+fn strip_quotes(s: &str) -> &str {
+    if s.len() >= 2 && s.starts_with('"') && s.ends_with('"') {
+        &s[1..s.len() - 1]
+    } else {
+        s
+    }
+}
+
+/// Tokenizes a placeholder's argument string into positional arguments and `key=value` pairs, as passed to
+/// [`PlaceholderFunction::placeholder_fn_handler_args`]. Arguments are whitespace-separated; either the whole
+/// argument or just a `key=value`'s value may be double-quoted to include whitespace, e.g.
+/// `join "a b" c sep=", "`. A word is treated as `key=value` only when the part before the first `=` is
+/// non-empty and unquoted, so a fully quoted word like `"a=b"` is always positional.
+fn tokenize_args(arg: &str) -> Result<(Vec<&str>, HashMap<&str, &str>), String> {
+    let mut positional = Vec::new();
+    let mut named = HashMap::new();
+
+    for word in split_args(arg)? {
+        if !word.starts_with('"') {
+            if let Some((key, value)) = split_once(word, '=') {
+                if !key.is_empty() {
+                    named.insert(key, strip_quotes(value));
+                    continue;
+                }
+            }
+        }
+        positional.push(strip_quotes(word));
+    }
+
+    Ok((positional, named))
+}
+
+/// Finds the `close` delimiter that closes a tag's content, starting at `content_start`. A nested `open`
+/// inside the content (e.g. the argument `<!$ upper <!$ name>>`) opens another level that must be closed by its
+/// own `close` first, so this tracks nesting depth rather than stopping at the first `close`.
+fn find_tag_close(inp_str: &str, content_start: usize, open: &str, close: &str) -> Option<usize> {
+    let mut depth = 1;
+    let mut pos = content_start;
+
+    while pos < inp_str.len() {
+        if inp_str[pos..].starts_with(close) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(pos);
+            }
+            pos += close.len();
+        } else if inp_str[pos..].starts_with(open) {
+            depth += 1;
+            pos += open.len();
+        } else {
+            pos += inp_str[pos..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+
+    None
+}
+
+/// Parses `inp_str` for placeholder tags delimited by `open`/`close` (e.g. `<!$`/`>`), returning the top-level
+/// tags found and the name of the first block left unclosed at the end of input, if any. A closing tag whose
+/// name doesn't match the innermost open block stops parsing, and that open block's name is returned instead.
+fn parse_placeholders<'i>(inp_str: &'i str, open: &str, close: &str, separator: char) -> (Vec<PlaceholderExpr<'i>>, Option<&'i str>) {
     let mut placeholders = Vec::new();
-    
-    let mut tmp_part = 0;
-    let mut tmp_tag_start = 0;
-    let mut tmp_is_in_tag = false;
-
-    for (i, c) in inp_str.chars().enumerate() {
-        if PLACEHOLDER_PARTS[tmp_part] == c {
-            if tmp_is_in_tag {
-                tmp_part += 1;
-            } else {
-                tmp_is_in_tag = true;
-                tmp_tag_start = i;
-                tmp_part += 1;
+    let mut stack: Vec<(&str, usize, usize)> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(rel) = inp_str[pos..].find(open) {
+        let tag_start = pos + rel;
+        let after_marker = tag_start + open.len();
+
+        let (is_open, is_close, is_raw, content_start) = if inp_str[after_marker..].starts_with('#') {
+            (true, false, false, after_marker + 1)
+        } else if inp_str[after_marker..].starts_with('/') {
+            (false, true, false, after_marker + 1)
+        } else if inp_str[after_marker..].starts_with('&') {
+            (false, false, true, after_marker + 1)
+        } else {
+            (false, false, false, after_marker)
+        };
+
+        if !inp_str[content_start..].starts_with(separator) {
+            pos = after_marker;
+            continue;
+        }
+        let content_start = content_start + separator.len_utf8();
+
+        let content_end = match find_tag_close(inp_str, content_start, open, close) {
+            Some(idx) => idx,
+            None => break,
+        };
+        let tag_end = content_end + close.len();
+        let content = &inp_str[content_start..content_end];
+
+        if is_open {
+            stack.push((content, tag_start, tag_end));
+        } else if is_close {
+            if let Some((open_content, open_start, body_start)) = stack.pop() {
+                let open_name = split_name_arg(open_content, separator).0;
+                let close_name = split_name_arg(content, separator).0;
+                if open_name != close_name {
+                    // Mismatched closing tag name, e.g. `<!$# each items>...<!$/ other>`: treat the block as
+                    // unclosed rather than silently accepting a typo'd/wrong closing tag.
+                    return (placeholders, Some(open_name));
+                }
+
+                if stack.is_empty() {
+                    placeholders.push(PlaceholderExpr {
+                        start_idx: open_start,
+                        end_idx: tag_end,
+                        content: open_content,
+                        body: Some(&inp_str[body_start..tag_start]),
+                        raw: false
+                    });
+                }
             }
-        } else if tmp_part != 4 {
-            tmp_part = 0;
-            tmp_is_in_tag = false;
+        } else if stack.is_empty() {
+            placeholders.push(PlaceholderExpr { start_idx: tag_start, end_idx: tag_end, content, body: None, raw: is_raw });
         }
 
-        if tmp_part == 5 {
-            placeholders.push(PlaceholderExpr {
-                start_idx: tmp_tag_start,
-                end_idx: i+1,
-                content: &inp_str[(tmp_tag_start+4)..i]
-            });
+        pos = tag_end;
+    }
+
+    let unclosed = stack.first().map(|(content, _, _)| split_name_arg(content, separator).0);
+    (placeholders, unclosed)
+}
+
+/// Internal error from [`expand_str`], kept separate from [`RenderError`] since expansion may run on strings
+/// (nested placeholder arguments, the output of a [`BlockFunction`]) that don't borrow from the original template.
+enum ExpandError {
+    Message(String),
+    RecursionLimit(usize)
+}
+
+/// Renderer configuration and function tables needed to resolve placeholders, bundled so [`expand_str`]'s
+/// recursive calls thread one context through instead of each renderer field positionally.
+struct ExpandCtx<'c, 'f> {
+    max_depth: usize,
+    open: &'c str,
+    close: &'c str,
+    separator: char,
+    placeholder_functions: &'c mut HashMap<&'f str, Box<dyn PlaceholderFunction>>,
+    block_functions: &'c mut HashMap<&'f str, Box<dyn BlockFunction>>,
+    escape_fn: &'c dyn Fn(&str) -> String
+}
 
-            tmp_part = 0;
-            tmp_is_in_tag = false;
+/// Renders `inp_str` against `ctx`'s function maps, resolving placeholders the same way [`TemplateRenderer::render`] does.
+/// Used to re-expand the output of a [`BlockFunction`] and to resolve nested placeholder arguments, e.g.
+/// `<!$ upper <!$ name>>`. `depth` counts levels of nesting seen so far and is checked against `ctx.max_depth`
+/// to guard against unbounded recursion.
+fn expand_str(inp_str: &str, depth: usize, ctx: &mut ExpandCtx<'_, '_>) -> Result<String, ExpandError> {
+    if depth > ctx.max_depth {
+        return Err(ExpandError::RecursionLimit(ctx.max_depth));
+    }
+
+    let (placeholders, unclosed) = parse_placeholders(inp_str, ctx.open, ctx.close, ctx.separator);
+    if let Some(name) = unclosed {
+        return Err(ExpandError::Message(format!("unclosed block '{}'", name)));
+    }
+
+    let mut out_str = String::new();
+    let mut last_end = 0;
+
+    for placeholder in &placeholders {
+        out_str.push_str(&inp_str[last_end..placeholder.start_idx]);
+        let (func, raw_arg) = split_name_arg(placeholder.content, ctx.separator);
+        let arg = if raw_arg.contains(ctx.open) {
+            expand_str(raw_arg, depth + 1, ctx)?
+        } else {
+            raw_arg.to_string()
+        };
+
+        if let Some(body) = placeholder.body {
+            if let Some(blockfn) = ctx.block_functions.get_mut(func) {
+                match blockfn.block_fn_handler(func, &arg, body) {
+                    Ok(output) => out_str.push_str(&expand_str(&output, depth + 1, ctx)?),
+                    Err(err) => return Err(ExpandError::Message(format!("error in block function {}: '{}'", func, err))),
+                }
+            } else {
+                return Err(ExpandError::Message(format!("unknown block function '{}'", func)));
+            }
+        } else if let Some(placeholderfn) = ctx.placeholder_functions.get_mut(func) {
+            let (positional, named) = tokenize_args(&arg).map_err(|err| ExpandError::Message(format!("error parsing arguments for function {}: '{}'", func, err)))?;
+            match placeholderfn.placeholder_fn_handler_args(func, &arg, &positional, &named) {
+                Ok(output) => out_str.push_str(&if placeholder.raw { output } else { (ctx.escape_fn)(&output) }),
+                Err(err) => return Err(ExpandError::Message(format!("error in placeholder function {}: '{}'", func, err))),
+            }
+        } else {
+            return Err(ExpandError::Message(format!("unknown function '{}'", func)));
         }
 
+        last_end = placeholder.end_idx;
     }
 
-    placeholders
+    out_str.push_str(&inp_str[last_end..]);
+    Ok(out_str)
 }
 
 /* waiting for https://github.com/rust-lang/rust/issues/74773 */
@@ -221,11 +619,69 @@ fn split_once<'a>(inp: &'a str, delim: char) -> Option<(&'a str, &'a str)> {
 
 #[test]
 fn placeholder_parsing() {
-    assert_eq!(parse_placeholders("<!$ name arg>"), [PlaceholderExpr { start_idx: 0, end_idx: 13, content: "name arg" }]);
-    assert_eq!(parse_placeholders("<!$ name1 arg1> <!$ name2 arg2>")
-        , [PlaceholderExpr { start_idx: 0, end_idx: 15, content: "name1 arg1" }
-        , PlaceholderExpr { start_idx: 16, end_idx: 31, content: "name2 arg2" }]);
-    assert_eq!(parse_placeholders("some text <!$ name1 arg1> other text <!$ name2 arg2> even more text")
-        , [PlaceholderExpr { start_idx: 10, end_idx: 25, content: "name1 arg1" }
-        , PlaceholderExpr { start_idx: 37, end_idx: 52, content: "name2 arg2" }]);
-}
\ No newline at end of file
+    assert_eq!(parse_placeholders("<!$ name arg>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR), (vec![PlaceholderExpr { start_idx: 0, end_idx: 13, content: "name arg", body: None, raw: false }], None));
+    assert_eq!(parse_placeholders("<!$ name1 arg1> <!$ name2 arg2>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR)
+        , (vec![PlaceholderExpr { start_idx: 0, end_idx: 15, content: "name1 arg1", body: None, raw: false }
+        , PlaceholderExpr { start_idx: 16, end_idx: 31, content: "name2 arg2", body: None, raw: false }], None));
+    assert_eq!(parse_placeholders("some text <!$ name1 arg1> other text <!$ name2 arg2> even more text", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR)
+        , (vec![PlaceholderExpr { start_idx: 10, end_idx: 25, content: "name1 arg1", body: None, raw: false }
+        , PlaceholderExpr { start_idx: 37, end_idx: 52, content: "name2 arg2", body: None, raw: false }], None));
+}
+
+#[test]
+fn block_placeholder_parsing() {
+    let (placeholders, unclosed) = parse_placeholders("<!$# each items>-<!$ name>-<!$/ each>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
+    assert_eq!(unclosed, None);
+    assert_eq!(placeholders, vec![PlaceholderExpr { start_idx: 0, end_idx: 37, content: "each items", body: Some("-<!$ name>-"), raw: false }]);
+
+    let (placeholders, unclosed) = parse_placeholders("<!$# each items>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
+    assert_eq!(placeholders, vec![]);
+    assert_eq!(unclosed, Some("each"));
+
+    let (placeholders, unclosed) = parse_placeholders("<!$# each items><!$# each inner><!$/ each>-<!$/ each>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
+    assert_eq!(unclosed, None);
+    assert_eq!(placeholders, vec![PlaceholderExpr { start_idx: 0, end_idx: 53, content: "each items", body: Some("<!$# each inner><!$/ each>-"), raw: false }]);
+}
+
+#[test]
+fn mismatched_block_name_is_unclosed() {
+    let (placeholders, unclosed) = parse_placeholders("<!$# each items>body<!$/ totally_different_name>after", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
+    assert_eq!(placeholders, vec![]);
+    assert_eq!(unclosed, Some("each"));
+}
+
+#[test]
+fn raw_placeholder_parsing() {
+    let (placeholders, unclosed) = parse_placeholders("<!$& markup arg>", DEFAULT_OPEN_DELIM, DEFAULT_CLOSE_DELIM, DEFAULT_SEPARATOR);
+    assert_eq!(unclosed, None);
+    assert_eq!(placeholders, vec![PlaceholderExpr { start_idx: 0, end_idx: 16, content: "markup arg", body: None, raw: true }]);
+}
+
+#[test]
+fn custom_delimiter_parsing() {
+    let (placeholders, unclosed) = parse_placeholders("{{ name arg}}", "{{", "}}", ' ');
+    assert_eq!(unclosed, None);
+    assert_eq!(placeholders, vec![PlaceholderExpr { start_idx: 0, end_idx: 13, content: "name arg", body: None, raw: false }]);
+}
+
+#[test]
+fn arg_tokenizing() {
+    let (positional, named) = tokenize_args("a b c").unwrap();
+    assert_eq!(positional, vec!["a", "b", "c"]);
+    assert!(named.is_empty());
+
+    let (positional, named) = tokenize_args("\"a b\" c").unwrap();
+    assert_eq!(positional, vec!["a b", "c"]);
+    assert!(named.is_empty());
+
+    let (positional, named) = tokenize_args("a key=value key2=\"quoted value\"").unwrap();
+    assert_eq!(positional, vec!["a"]);
+    assert_eq!(named.get("key"), Some(&"value"));
+    assert_eq!(named.get("key2"), Some(&"quoted value"));
+
+    let (positional, named) = tokenize_args("\"a=b\"").unwrap();
+    assert_eq!(positional, vec!["a=b"]);
+    assert!(named.is_empty());
+
+    assert!(tokenize_args("\"unterminated").is_err());
+}
@@ -55,4 +55,199 @@ fn function_returning_err() {
         crate::RenderError::FunctionError("err", err_str) => err_str == "test error",
         _ => false
     });
+}
+
+struct Each(Vec<String>);
+impl crate::BlockFunction for Each {
+    fn block_fn_handler<'a>(&mut self, _name: &'a str, _arg: &'a str, body: &'a str) -> Result<String, String> {
+        Ok(self.0.iter().map(|item| body.replace("<!$ item>", item)).collect())
+    }
+}
+
+#[test]
+fn each_block_function() {
+    let mut tr = crate::TemplateRenderer::with_template("before <!$# each items>[<!$ item>]<!$/ each> after");
+    tr.set_block_fn("each", Box::new(Each(vec!["a".to_string(), "b".to_string(), "c".to_string()])));
+    assert_eq!(tr.render().unwrap(), "before [a][b][c] after");
+}
+
+#[test]
+fn unclosed_block() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$# each items>no closing tag");
+    assert!(match tr.render().unwrap_err() {
+        crate::RenderError::UnclosedBlock("each") => true,
+        _ => false
+    });
+}
+
+#[test]
+fn mismatched_closing_block_name_errors() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$# each items>body<!$/ totally_different_name>after");
+    tr.set_block_fn("each", Box::new(Each(vec!["a".to_string()])));
+    assert!(match tr.render().unwrap_err() {
+        crate::RenderError::UnclosedBlock("each") => true,
+        _ => false
+    });
+}
+
+struct Upper();
+impl crate::PlaceholderFunction for Upper {
+    fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+        Ok(arg.to_uppercase())
+    }
+}
+
+#[test]
+fn nested_placeholder() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ upper <!$ echo hello>>");
+    tr.set_placeholder_fn("upper", Box::new(Upper()));
+    tr.set_placeholder_fn("echo", Box::new(Echo()));
+    assert_eq!(tr.render().unwrap(), "HELLO");
+}
+
+#[test]
+fn recursion_limit() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ upper <!$ upper <!$ upper name>>>");
+    tr.set_placeholder_fn("upper", Box::new(Upper()));
+    tr.set_max_depth(1);
+    assert!(match tr.render().unwrap_err() {
+        crate::RenderError::RecursionLimit(1) => true,
+        _ => false
+    });
+}
+
+struct Bold();
+impl crate::PlaceholderFunction for Bold {
+    fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, _arg: &'a str) -> Result<String, String> {
+        Ok("<b>bold</b>".to_string())
+    }
+}
+
+#[test]
+fn default_escape_fn_is_identity() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ bold>");
+    tr.set_placeholder_fn("bold", Box::new(Bold()));
+    assert_eq!(tr.render().unwrap(), "<b>bold</b>");
+}
+
+#[test]
+fn html_escape_fn() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ bold>");
+    tr.set_placeholder_fn("bold", Box::new(Bold()));
+    tr.set_escape_fn(Box::new(crate::html_escape));
+    assert_eq!(tr.render().unwrap(), "&lt;b&gt;bold&lt;/b&gt;");
+}
+
+#[test]
+fn raw_tag_bypasses_escaping() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ bold>, <!$& bold>");
+    tr.set_placeholder_fn("bold", Box::new(Bold()));
+    tr.set_escape_fn(Box::new(crate::html_escape));
+    assert_eq!(tr.render().unwrap(), "&lt;b&gt;bold&lt;/b&gt;, <b>bold</b>");
+}
+
+#[cfg(feature = "script")]
+#[test]
+fn script_function() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ upper arg>");
+    tr.register_script_fn("upper", "arg.to_upper()").unwrap();
+    assert_eq!(tr.render().unwrap(), "ARG");
+}
+
+#[cfg(feature = "script")]
+#[test]
+fn script_function_compile_error() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ broken>");
+    assert!(tr.register_script_fn("broken", "(((").is_err());
+}
+
+struct StaticSource(&'static str);
+impl crate::TemplateSource for StaticSource {
+    fn get_template(&self, id: &str) -> Option<&'static str> {
+        if id == "welcome" { Some(self.0) } else { None }
+    }
+}
+
+#[test]
+fn registry_falls_back_to_next_locale() {
+    let mut registry = crate::TemplateRegistry::new();
+    registry.set_placeholder_fn("echo", Box::new(Echo()));
+    registry.add_source("de", Box::new(StaticSource("<!$ echo Hallo>")));
+    registry.add_source("en", Box::new(StaticSource("<!$ echo Hello>")));
+
+    assert_eq!(registry.render("welcome", &["fr", "de", "en"]).unwrap(), "Hallo");
+    assert_eq!(registry.render("welcome", &["fr", "en"]).unwrap(), "Hello");
+}
+
+#[test]
+fn registry_falls_back_on_render_error() {
+    let mut registry = crate::TemplateRegistry::new();
+    registry.add_source("de", Box::new(StaticSource("<!$ echo Hallo>")));
+    registry.add_source("en", Box::new(StaticSource("<!$ echo Hello>")));
+    registry.set_placeholder_fn("echo", Box::new(RetErr()));
+
+    assert!(match registry.render("welcome", &["de", "en"]).unwrap_err() {
+        crate::RenderError::AllSourcesFailed(errs) => errs.len() == 2,
+        _ => false
+    });
+}
+
+#[test]
+fn custom_delimiters() {
+    let mut tr = crate::TemplateRenderer::with_template("{{ echo Hello}}, World!").with_delimiters("{{", "}}", ' ');
+    tr.set_placeholder_fn("echo", Box::new(Echo()));
+    assert_eq!(tr.render().unwrap(), "Hello, World!");
+}
+
+#[test]
+#[should_panic(expected = "open delimiter must not be empty")]
+fn empty_open_delimiter_panics() {
+    crate::TemplateRenderer::with_template("hello world").with_delimiters("", ">", ' ');
+}
+
+struct Greet();
+impl crate::PlaceholderFunction for Greet {
+    fn placeholder_fn_handler<'a>(&mut self, _name: &'a str, arg: &'a str) -> Result<String, String> {
+        Ok(arg.to_string())
+    }
+
+    fn placeholder_fn_handler_args<'a>(&mut self, _name: &'a str, _arg: &'a str, positional: &[&'a str], named: &std::collections::HashMap<&'a str, &'a str>) -> Result<String, String> {
+        let greeting = named.get("greeting").copied().unwrap_or("Hello");
+        Ok(format!("{}, {}!", greeting, positional.join(" ")))
+    }
+}
+
+#[test]
+fn structured_args() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ greet \"John Smith\" greeting=Hi>");
+    tr.set_placeholder_fn("greet", Box::new(Greet()));
+    assert_eq!(tr.render().unwrap(), "Hi, John Smith!");
+}
+
+#[test]
+fn unimplemented_args_handler_falls_back_to_raw_arg() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ echo \"a b\" c>");
+    tr.set_placeholder_fn("echo", Box::new(Echo()));
+    assert_eq!(tr.render().unwrap(), "\"a b\" c");
+}
+
+#[test]
+fn malformed_quoting_returns_arg_parse_error() {
+    let mut tr = crate::TemplateRenderer::with_template("<!$ echo \"unterminated>");
+    tr.set_placeholder_fn("echo", Box::new(Echo()));
+    assert!(match tr.render().unwrap_err() {
+        crate::RenderError::ArgParse("echo", _) => true,
+        _ => false
+    });
+}
+
+#[test]
+fn registry_all_sources_failed_when_no_template_found() {
+    let mut registry = crate::TemplateRegistry::new();
+    registry.add_source("en", Box::new(StaticSource("<!$ echo Hello>")));
+
+    assert!(match registry.render("missing", &["en"]).unwrap_err() {
+        crate::RenderError::AllSourcesFailed(errs) => errs.is_empty(),
+        _ => false
+    });
 }
\ No newline at end of file